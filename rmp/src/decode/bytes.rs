@@ -0,0 +1,170 @@
+#[cfg(feature = "std")]
+use std::fmt::{self, Display, Formatter};
+#[cfg(not(feature = "std"))]
+use core::fmt::{self, Display, Formatter};
+
+#[cfg(feature = "std")]
+use std::error;
+
+use super::RmpRead;
+
+/// An error returned by `Bytes` when a read runs past the end of the wrapped slice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EndOfBuffer;
+
+impl Display for EndOfBuffer {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        "unexpected end of buffer".fmt(f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for EndOfBuffer {
+    fn description(&self) -> &str {
+        "unexpected end of buffer"
+    }
+}
+
+/// A cursor over an in-memory byte slice that implements `RmpRead`.
+///
+/// Unlike `std::io::Read`, reading from `Bytes` never performs I/O or allocates, which makes it
+/// usable to decode MessagePack values in `no_std` environments.
+#[derive(Debug)]
+pub struct Bytes<'a> {
+    slice: &'a [u8],
+}
+
+impl<'a> Bytes<'a> {
+    /// Wraps the given byte slice for reading.
+    pub fn new(slice: &'a [u8]) -> Bytes<'a> {
+        Bytes { slice: slice }
+    }
+
+    /// Returns the unconsumed remainder of the underlying slice.
+    pub fn remainder(&self) -> &'a [u8] {
+        self.slice
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], EndOfBuffer> {
+        if self.slice.len() < n {
+            return Err(EndOfBuffer);
+        }
+
+        let (head, tail) = self.slice.split_at(n);
+        self.slice = tail;
+
+        Ok(head)
+    }
+}
+
+impl<'a> RmpRead for Bytes<'a> {
+    type Error = EndOfBuffer;
+
+    fn read_u8(&mut self) -> Result<u8, EndOfBuffer> {
+        Ok(try!(self.take(1))[0])
+    }
+
+    fn read_data_u16(&mut self) -> Result<u16, EndOfBuffer> {
+        let buf = try!(self.take(2));
+        Ok(((buf[0] as u16) << 8) | (buf[1] as u16))
+    }
+
+    fn read_data_u32(&mut self) -> Result<u32, EndOfBuffer> {
+        let buf = try!(self.take(4));
+        Ok(((buf[0] as u32) << 24) | ((buf[1] as u32) << 16) | ((buf[2] as u32) << 8) |
+           (buf[3] as u32))
+    }
+
+    fn read_data_u64(&mut self) -> Result<u64, EndOfBuffer> {
+        let buf = try!(self.take(8));
+        let mut val: u64 = 0;
+        for &byte in buf {
+            val = (val << 8) | (byte as u64);
+        }
+        Ok(val)
+    }
+
+    fn read_data_i8(&mut self) -> Result<i8, EndOfBuffer> {
+        Ok(try!(self.read_u8()) as i8)
+    }
+
+    fn read_data_i16(&mut self) -> Result<i16, EndOfBuffer> {
+        Ok(try!(self.read_data_u16()) as i16)
+    }
+
+    fn read_data_i32(&mut self) -> Result<i32, EndOfBuffer> {
+        Ok(try!(self.read_data_u32()) as i32)
+    }
+
+    fn read_data_i64(&mut self) -> Result<i64, EndOfBuffer> {
+        Ok(try!(self.read_data_u64()) as i64)
+    }
+
+    fn read_exact_buf(&mut self, buf: &mut [u8]) -> Result<(), EndOfBuffer> {
+        let data = try!(self.take(buf.len()));
+        buf.copy_from_slice(data);
+        Ok(())
+    }
+
+    fn skip_bytes(&mut self, len: u64) -> Result<(), EndOfBuffer> {
+        self.take(len as usize).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_data_u16_matches_io_read() {
+        let raw = [0x01, 0x02];
+
+        let mut bytes = Bytes::new(&raw);
+        let from_bytes = bytes.read_data_u16().unwrap();
+
+        let from_io = (&raw[..]).read_data_u16().unwrap();
+
+        assert_eq!(from_io, from_bytes);
+        assert_eq!(0x0102, from_bytes);
+    }
+
+    #[test]
+    fn read_data_u64_big_endian() {
+        let raw = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00];
+        let mut bytes = Bytes::new(&raw);
+
+        assert_eq!(256, bytes.read_data_u64().unwrap());
+    }
+
+    #[test]
+    fn remainder_advances_as_bytes_are_consumed() {
+        let raw = [0xaa, 0xbb, 0xcc];
+        let mut bytes = Bytes::new(&raw);
+
+        bytes.read_u8().unwrap();
+
+        assert_eq!(&raw[1..], bytes.remainder());
+    }
+
+    #[test]
+    fn read_past_end_returns_end_of_buffer() {
+        let raw = [0x01];
+        let mut bytes = Bytes::new(&raw);
+
+        assert_eq!(Err(EndOfBuffer), bytes.read_data_u16());
+        // The short read must not have consumed anything.
+        assert_eq!(&raw[..], bytes.remainder());
+    }
+
+    #[test]
+    fn read_exact_buf_fills_buffer() {
+        let raw = [0x01, 0x02, 0x03];
+        let mut bytes = Bytes::new(&raw);
+
+        let mut buf = [0u8; 3];
+        bytes.read_exact_buf(&mut buf).unwrap();
+
+        assert_eq!(raw, buf);
+        assert!(bytes.remainder().is_empty());
+    }
+}
@@ -0,0 +1,187 @@
+use Marker;
+use super::{read_marker, read_data_i8, read_data_u8, read_data_u16, read_data_u32, RmpRead,
+            ValueReadError};
+
+/// Represents MessagePack extension type metadata, as read by `read_ext_meta`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExtMeta {
+    /// Type of the extension.
+    pub typeid: i8,
+    /// Size of the extension payload, in bytes.
+    pub size: u32,
+}
+
+/// Attempts to read and decode an extension type metadata from the given reader.
+///
+/// # Errors
+///
+/// This function will return `ValueReadError` on any I/O error while reading either the type
+/// marker, the length (for the variable-length `Ext8`/`Ext16`/`Ext32` markers) or the type id,
+/// except the EINTR, which is handled internally.
+///
+/// It also returns `ValueReadError::TypeMismatch` if the actual type is not equal with the
+/// expected one, indicating you with the actual type.
+///
+/// # Note
+///
+/// This function will silently retry on every EINTR received from the underlying `Read` until
+/// successful read.
+pub fn read_ext_meta<R: RmpRead>(rd: &mut R) -> Result<ExtMeta, ValueReadError<R::Error>> {
+    let size = match try!(read_marker(rd)) {
+        Marker::FixExt1 => 1,
+        Marker::FixExt2 => 2,
+        Marker::FixExt4 => 4,
+        Marker::FixExt8 => 8,
+        Marker::FixExt16 => 16,
+        Marker::Ext8 => try!(read_data_u8(rd)) as u32,
+        Marker::Ext16 => try!(read_data_u16(rd)) as u32,
+        Marker::Ext32 => try!(read_data_u32(rd)),
+        marker => return Err(ValueReadError::TypeMismatch(marker)),
+    };
+
+    let typeid = try!(read_data_i8(rd));
+    let meta = ExtMeta {
+        typeid: typeid,
+        size: size,
+    };
+
+    Ok(meta)
+}
+
+/// Attempts to read a fixext1 type from the given reader and to decode it as a `(type, data)`
+/// pair.
+pub fn read_fixext1<R: RmpRead>(rd: &mut R) -> Result<(i8, [u8; 1]), ValueReadError<R::Error>> {
+    match try!(read_marker(rd)) {
+        Marker::FixExt1 => {
+            let typeid = try!(read_data_i8(rd));
+            let data = try!(read_data_u8(rd));
+
+            Ok((typeid, [data]))
+        }
+        marker => Err(ValueReadError::TypeMismatch(marker)),
+    }
+}
+
+/// Attempts to read a fixext2 type from the given reader and to decode it as a `(type, data)`
+/// pair.
+pub fn read_fixext2<R: RmpRead>(rd: &mut R) -> Result<(i8, [u8; 2]), ValueReadError<R::Error>> {
+    match try!(read_marker(rd)) {
+        Marker::FixExt2 => {
+            let typeid = try!(read_data_i8(rd));
+
+            let mut buf = [0u8; 2];
+            try!(rd.read_exact_buf(&mut buf).map_err(ValueReadError::InvalidDataRead));
+
+            Ok((typeid, buf))
+        }
+        marker => Err(ValueReadError::TypeMismatch(marker)),
+    }
+}
+
+/// Attempts to read a fixext4 type from the given reader and to decode it as a `(type, data)`
+/// pair.
+pub fn read_fixext4<R: RmpRead>(rd: &mut R) -> Result<(i8, [u8; 4]), ValueReadError<R::Error>> {
+    match try!(read_marker(rd)) {
+        Marker::FixExt4 => {
+            let typeid = try!(read_data_i8(rd));
+
+            let mut buf = [0u8; 4];
+            try!(rd.read_exact_buf(&mut buf).map_err(ValueReadError::InvalidDataRead));
+
+            Ok((typeid, buf))
+        }
+        marker => Err(ValueReadError::TypeMismatch(marker)),
+    }
+}
+
+/// Attempts to read a fixext8 type from the given reader and to decode it as a `(type, data)`
+/// pair.
+pub fn read_fixext8<R: RmpRead>(rd: &mut R) -> Result<(i8, [u8; 8]), ValueReadError<R::Error>> {
+    match try!(read_marker(rd)) {
+        Marker::FixExt8 => {
+            let typeid = try!(read_data_i8(rd));
+
+            let mut buf = [0u8; 8];
+            try!(rd.read_exact_buf(&mut buf).map_err(ValueReadError::InvalidDataRead));
+
+            Ok((typeid, buf))
+        }
+        marker => Err(ValueReadError::TypeMismatch(marker)),
+    }
+}
+
+/// Attempts to read a fixext16 type from the given reader and to decode it as a `(type, data)`
+/// pair.
+pub fn read_fixext16<R: RmpRead>(rd: &mut R) -> Result<(i8, [u8; 16]), ValueReadError<R::Error>> {
+    match try!(read_marker(rd)) {
+        Marker::FixExt16 => {
+            let typeid = try!(read_data_i8(rd));
+
+            let mut buf = [0u8; 16];
+            try!(rd.read_exact_buf(&mut buf).map_err(ValueReadError::InvalidDataRead));
+
+            Ok((typeid, buf))
+        }
+        marker => Err(ValueReadError::TypeMismatch(marker)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_ext_meta_reads_fixext_size_from_marker() {
+        let buf = [0xd4, 0x01];
+        let meta = read_ext_meta(&mut &buf[..]).unwrap();
+
+        assert_eq!(ExtMeta { typeid: 1, size: 1 }, meta);
+    }
+
+    #[test]
+    fn read_ext_meta_reads_ext32_length_prefix() {
+        let buf = [0xc9, 0x00, 0x00, 0x01, 0x00, 0x2a];
+        let meta = read_ext_meta(&mut &buf[..]).unwrap();
+
+        assert_eq!(ExtMeta { typeid: 0x2a, size: 256 }, meta);
+    }
+
+    #[test]
+    fn read_ext_meta_type_mismatch_on_other_marker() {
+        let buf = [0xc0];
+
+        match read_ext_meta(&mut &buf[..]) {
+            Err(ValueReadError::TypeMismatch(Marker::Null)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_fixext1_returns_array_payload() {
+        let buf = [0xd4, 0x07, 0xff];
+        let (typeid, data) = read_fixext1(&mut &buf[..]).unwrap();
+
+        assert_eq!(7, typeid);
+        assert_eq!([0xff], data);
+    }
+
+    #[test]
+    fn read_fixext2_reads_fixed_payload() {
+        let buf = [0xd5, 0x01, 0xde, 0xad];
+        let (typeid, data) = read_fixext2(&mut &buf[..]).unwrap();
+
+        assert_eq!(1, typeid);
+        assert_eq!([0xde, 0xad], data);
+    }
+
+    #[test]
+    fn read_fixext16_reads_fixed_payload() {
+        let mut buf = vec![0xd8, 0x2a];
+        buf.extend_from_slice(&[0x01; 16]);
+
+        let (typeid, data) = read_fixext16(&mut &buf[..]).unwrap();
+
+        assert_eq!(0x2a, typeid);
+        assert_eq!([0x01; 16], data);
+    }
+}
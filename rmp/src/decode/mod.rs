@@ -8,39 +8,219 @@
 //! I/O error and simultaneously be a recoverable state (for example, when reading from
 //! non-blocking socket and it returns EWOULDBLOCK) be sure that you buffer the data externally
 //! to avoid data loss (using `BufRead` readers with manual consuming or some other way).
+//!
+//! # Reading from other sources than `Read`
+//!
+//! Every function in this module is generic over `RmpRead` rather than being hardcoded to
+//! `std::io::Read`. This lets the same decoding logic run against an in-memory byte slice via
+//! `Bytes` without pulling in `std` at all, in addition to the usual `io::Read` path.
+//!
+//! `read_value`/`Value` are the exception: decoding into an owned, dynamically-typed value
+//! requires allocation (`String`, `Vec`), so that part of the module is gated on the `std`
+//! feature rather than offered as a `no_std` API.
 
+mod bytes;
+mod ext;
 mod sint;
+mod skip;
+mod str;
 mod uint;
+#[cfg(feature = "std")]
+mod value;
 
+pub use self::bytes::{Bytes, EndOfBuffer};
+pub use self::ext::{read_ext_meta, read_fixext1, read_fixext2, read_fixext4, read_fixext8,
+                     read_fixext16, ExtMeta};
 pub use self::sint::{read_nfix, read_i8, read_i16, read_i32, read_i64};
+pub use self::skip::skip_value;
+pub use self::str::{read_str, read_str_from_slice, read_str_len, DecodeStringError};
 pub use self::uint::{read_pfix, read_u8, read_u16, read_u32, read_u64};
+#[cfg(feature = "std")]
+pub use self::value::{read_value, read_value_with_max_depth, DecodeValueError, Integer, Value,
+                       DEFAULT_MAX_DEPTH};
+
+#[cfg(feature = "std")]
+use std::cmp;
+#[cfg(not(feature = "std"))]
+use core::cmp;
+
+#[cfg(feature = "std")]
+use std::fmt::{self, Debug, Display, Formatter};
+#[cfg(not(feature = "std"))]
+use core::fmt::{self, Debug, Display, Formatter};
 
+#[cfg(feature = "std")]
 use std::error;
-use std::fmt::{self, Display, Formatter};
+#[cfg(feature = "std")]
 use std::io::Read;
 
+#[cfg(feature = "std")]
 use byteorder::{self, ReadBytesExt};
 
 use Marker;
 
-/// An error that can occur when attempting to read bytes from the reader.
+mod private {
+    /// Seals `RmpRead` so that it can only be implemented by this crate.
+    pub trait Sealed {}
+
+    #[cfg(feature = "std")]
+    impl<R: ::std::io::Read> Sealed for R {}
+    impl<'a> Sealed for super::Bytes<'a> {}
+}
+
+/// A marker trait for errors returned by an `RmpRead` implementation.
+///
+/// It exists so that decoding functions can be generic over the reader's error type without
+/// depending on `std::error::Error`, which isn't available in `no_std` environments.
+pub trait RmpReadErr: Debug + Display {}
+
+#[cfg(feature = "std")]
+impl RmpReadErr for Error {}
+impl RmpReadErr for EndOfBuffer {}
+
+/// A data source that MessagePack values can be decoded from.
+///
+/// This trait abstracts over `std::io::Read` and an in-memory byte slice (see `Bytes`), which
+/// allows the functions in this module to run in `no_std` environments when decoding from a
+/// buffer that is already fully in memory. It is sealed and cannot be implemented outside of this
+/// crate.
+pub trait RmpRead: private::Sealed {
+    /// The error returned when a read could not be completed.
+    type Error: RmpReadErr;
+
+    /// Reads exactly one byte from the underlying source.
+    fn read_u8(&mut self) -> Result<u8, Self::Error>;
+
+    /// Reads a big-endian encoded `u16` from the underlying source.
+    fn read_data_u16(&mut self) -> Result<u16, Self::Error>;
+
+    /// Reads a big-endian encoded `u32` from the underlying source.
+    fn read_data_u32(&mut self) -> Result<u32, Self::Error>;
+
+    /// Reads a big-endian encoded `u64` from the underlying source.
+    fn read_data_u64(&mut self) -> Result<u64, Self::Error>;
+
+    /// Reads a single byte from the underlying source and interprets it as an `i8`.
+    fn read_data_i8(&mut self) -> Result<i8, Self::Error>;
+
+    /// Reads a big-endian encoded `i16` from the underlying source.
+    fn read_data_i16(&mut self) -> Result<i16, Self::Error>;
+
+    /// Reads a big-endian encoded `i32` from the underlying source.
+    fn read_data_i32(&mut self) -> Result<i32, Self::Error>;
+
+    /// Reads a big-endian encoded `i64` from the underlying source.
+    fn read_data_i64(&mut self) -> Result<i64, Self::Error>;
+
+    /// Fills `buf` entirely from the underlying source.
+    fn read_exact_buf(&mut self, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Discards exactly `len` bytes from the underlying source without retaining them.
+    ///
+    /// The default implementation copies through a small fixed-size stack buffer, which works for
+    /// any `RmpRead` implementation including `no_std` ones. Implementations that can discard
+    /// bytes more cheaply (for example `io::Read`, via `io::copy` into `io::sink`) may override
+    /// it.
+    fn skip_bytes(&mut self, len: u64) -> Result<(), Self::Error> {
+        let mut buf = [0u8; 1024];
+        let mut remaining = len;
+
+        while remaining > 0 {
+            let chunk = cmp::min(remaining, buf.len() as u64) as usize;
+            try!(self.read_exact_buf(&mut buf[..chunk]));
+            remaining -= chunk as u64;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> RmpRead for R {
+    type Error = Error;
+
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        ReadBytesExt::read_u8(self)
+    }
+
+    fn read_data_u16(&mut self) -> Result<u16, Error> {
+        self.read_u16::<byteorder::BigEndian>()
+    }
+
+    fn read_data_u32(&mut self) -> Result<u32, Error> {
+        self.read_u32::<byteorder::BigEndian>()
+    }
+
+    fn read_data_u64(&mut self) -> Result<u64, Error> {
+        self.read_u64::<byteorder::BigEndian>()
+    }
+
+    fn read_data_i8(&mut self) -> Result<i8, Error> {
+        self.read_i8()
+    }
+
+    fn read_data_i16(&mut self) -> Result<i16, Error> {
+        self.read_i16::<byteorder::BigEndian>()
+    }
+
+    fn read_data_i32(&mut self) -> Result<i32, Error> {
+        self.read_i32::<byteorder::BigEndian>()
+    }
+
+    fn read_data_i64(&mut self) -> Result<i64, Error> {
+        self.read_i64::<byteorder::BigEndian>()
+    }
+
+    fn read_exact_buf(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        self.read_exact(buf)
+    }
+
+    fn skip_bytes(&mut self, len: u64) -> Result<(), Error> {
+        let mut take = self.take(len);
+        let copied = try!(::std::io::copy(&mut take, &mut ::std::io::sink()));
+
+        if copied != len {
+            return Err(::std::io::Error::from(::std::io::ErrorKind::UnexpectedEof));
+        }
+
+        Ok(())
+    }
+}
+
+/// An error that can occur when attempting to read bytes from an `io::Read` reader.
+///
+/// This is the `RmpRead::Error` of every `std::io::Read` implementation, and remains the default
+/// error parameter of `ValueReadError`/`NumValueReadError` so that existing code using those
+/// types without an explicit type parameter keeps compiling unchanged.
+#[cfg(feature = "std")]
 pub type Error = ::std::io::Error;
 
+/// The default error parameter of `ValueReadError`/`NumValueReadError`/`DecodeStringError`.
+///
+/// This is `Error` (`std::io::Error`) when `std` is available, matching every `io::Read`
+/// implementation's `RmpRead::Error`. Without `std` there is no `io::Error` to default to, so it
+/// falls back to `EndOfBuffer`, the error type `Bytes` actually produces.
+#[cfg(feature = "std")]
+type DefaultError = Error;
+#[cfg(not(feature = "std"))]
+type DefaultError = EndOfBuffer;
+
 /// An error that can occur when attempting to read a MessagePack marker from the reader.
-struct MarkerReadError(Error);
+struct MarkerReadError<E: RmpReadErr>(E);
 
 /// An error which can occur when attempting to read a MessagePack value from the reader.
 #[derive(Debug)]
-pub enum ValueReadError {
+pub enum ValueReadError<E: RmpReadErr = DefaultError> {
     /// Failed to read the marker.
-    InvalidMarkerRead(Error),
+    InvalidMarkerRead(E),
     /// Failed to read the data.
-    InvalidDataRead(Error),
+    InvalidDataRead(E),
     /// The type decoded isn't match with the expected one.
     TypeMismatch(Marker),
 }
 
-impl error::Error for ValueReadError {
+#[cfg(feature = "std")]
+impl<E: RmpReadErr + error::Error> error::Error for ValueReadError<E> {
     fn description(&self) -> &str {
         match *self {
             ValueReadError::InvalidMarkerRead(..) => "failed to read MessagePack marker",
@@ -60,28 +240,38 @@ impl error::Error for ValueReadError {
     }
 }
 
-impl Display for ValueReadError {
+impl<E: RmpReadErr> Display for ValueReadError<E> {
     fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
-        error::Error::description(self).fmt(f)
+        match *self {
+            ValueReadError::InvalidMarkerRead(ref err) => {
+                write!(f, "failed to read MessagePack marker: {}", err)
+            }
+            ValueReadError::InvalidDataRead(ref err) => {
+                write!(f, "failed to read MessagePack data: {}", err)
+            }
+            ValueReadError::TypeMismatch(..) => {
+                write!(f, "the type decoded isn't match with the expected one")
+            }
+        }
     }
 }
 
-impl From<MarkerReadError> for ValueReadError {
-    fn from(err: MarkerReadError) -> ValueReadError {
+impl<E: RmpReadErr> From<MarkerReadError<E>> for ValueReadError<E> {
+    fn from(err: MarkerReadError<E>) -> ValueReadError<E> {
         match err {
             MarkerReadError(err) => ValueReadError::InvalidMarkerRead(err),
         }
     }
 }
 
-impl From<Error> for MarkerReadError {
-    fn from(err: Error) -> MarkerReadError {
+impl<E: RmpReadErr> From<E> for MarkerReadError<E> {
+    fn from(err: E) -> MarkerReadError<E> {
         MarkerReadError(err)
     }
 }
 
 /// Attempts to read a single byte from the given reader and to decode it as a MessagePack marker.
-fn read_marker<R: Read>(rd: &mut R) -> Result<Marker, MarkerReadError> {
+fn read_marker<R: RmpRead>(rd: &mut R) -> Result<Marker, MarkerReadError<R::Error>> {
     Ok(Marker::from_u8(try!(rd.read_u8())))
 }
 
@@ -101,7 +291,7 @@ fn read_marker<R: Read>(rd: &mut R) -> Result<Marker, MarkerReadError> {
 ///
 /// This function will silently retry on every EINTR received from the underlying `Read` until
 /// successful read.
-pub fn read_nil<R: Read>(rd: &mut R) -> Result<(), ValueReadError> {
+pub fn read_nil<R: RmpRead>(rd: &mut R) -> Result<(), ValueReadError<R::Error>> {
     match try!(read_marker(rd)) {
         Marker::Null => Ok(()),
         marker => Err(ValueReadError::TypeMismatch(marker)),
@@ -125,7 +315,7 @@ pub fn read_nil<R: Read>(rd: &mut R) -> Result<(), ValueReadError> {
 ///
 /// This function will silently retry on every EINTR received from the underlying `Read` until
 /// successful read.
-pub fn read_bool<R: Read>(rd: &mut R) -> Result<bool, ValueReadError> {
+pub fn read_bool<R: RmpRead>(rd: &mut R) -> Result<bool, ValueReadError<R::Error>> {
     match try!(read_marker(rd)) {
         Marker::True => Ok(true),
         Marker::False => Ok(false),
@@ -135,18 +325,19 @@ pub fn read_bool<R: Read>(rd: &mut R) -> Result<bool, ValueReadError> {
 
 /// An error which can occur when attempting to read a MessagePack numeric value from the reader.
 #[derive(Debug)]
-pub enum NumValueReadError {
+pub enum NumValueReadError<E: RmpReadErr = DefaultError> {
     /// Failed to read the marker.
-    InvalidMarkerRead(Error),
+    InvalidMarkerRead(E),
     /// Failed to read the data.
-    InvalidDataRead(Error),
+    InvalidDataRead(E),
     /// The type decoded isn't match with the expected one.
     TypeMismatch(Marker),
     /// Out of range integral type conversion attempted.
     OutOfRange,
 }
 
-impl error::Error for NumValueReadError {
+#[cfg(feature = "std")]
+impl<E: RmpReadErr + error::Error> error::Error for NumValueReadError<E> {
     fn description(&self) -> &str {
         match *self {
             NumValueReadError::InvalidMarkerRead(..) => "failed to read MessagePack marker",
@@ -168,50 +359,63 @@ impl error::Error for NumValueReadError {
     }
 }
 
-impl Display for NumValueReadError {
+impl<E: RmpReadErr> Display for NumValueReadError<E> {
     fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
-        error::Error::description(self).fmt(f)
+        match *self {
+            NumValueReadError::InvalidMarkerRead(ref err) => {
+                write!(f, "failed to read MessagePack marker: {}", err)
+            }
+            NumValueReadError::InvalidDataRead(ref err) => {
+                write!(f, "failed to read MessagePack data: {}", err)
+            }
+            NumValueReadError::TypeMismatch(..) => {
+                write!(f, "the type decoded isn't match with the expected one")
+            }
+            NumValueReadError::OutOfRange => {
+                write!(f, "out of range integral type conversion attempted")
+            }
+        }
     }
 }
 
-impl From<MarkerReadError> for NumValueReadError {
-    fn from(err: MarkerReadError) -> NumValueReadError {
+impl<E: RmpReadErr> From<MarkerReadError<E>> for NumValueReadError<E> {
+    fn from(err: MarkerReadError<E>) -> NumValueReadError<E> {
         match err {
             MarkerReadError(err) => NumValueReadError::InvalidMarkerRead(err),
         }
     }
 }
 
-// Helper functions to map I/O error into the `InvalidDataRead` error.
+// Helper functions to map a read error into the `InvalidDataRead` error.
 
-fn read_data_i8<R: Read>(rd: &mut R) -> Result<i8, ValueReadError> {
-    rd.read_i8().map_err(ValueReadError::InvalidDataRead)
+fn read_data_i8<R: RmpRead>(rd: &mut R) -> Result<i8, ValueReadError<R::Error>> {
+    rd.read_data_i8().map_err(ValueReadError::InvalidDataRead)
 }
 
-fn read_data_i16<R: Read>(rd: &mut R) -> Result<i16, ValueReadError> {
-    rd.read_i16::<byteorder::BigEndian>().map_err(ValueReadError::InvalidDataRead)
+fn read_data_i16<R: RmpRead>(rd: &mut R) -> Result<i16, ValueReadError<R::Error>> {
+    rd.read_data_i16().map_err(ValueReadError::InvalidDataRead)
 }
 
-fn read_data_i32<R: Read>(rd: &mut R) -> Result<i32, ValueReadError> {
-    rd.read_i32::<byteorder::BigEndian>().map_err(ValueReadError::InvalidDataRead)
+fn read_data_i32<R: RmpRead>(rd: &mut R) -> Result<i32, ValueReadError<R::Error>> {
+    rd.read_data_i32().map_err(ValueReadError::InvalidDataRead)
 }
 
-fn read_data_i64<R: Read>(rd: &mut R) -> Result<i64, ValueReadError> {
-    rd.read_i64::<byteorder::BigEndian>().map_err(ValueReadError::InvalidDataRead)
+fn read_data_i64<R: RmpRead>(rd: &mut R) -> Result<i64, ValueReadError<R::Error>> {
+    rd.read_data_i64().map_err(ValueReadError::InvalidDataRead)
 }
 
-fn read_data_u8<R: Read>(rd: &mut R) -> Result<u8, ValueReadError> {
+fn read_data_u8<R: RmpRead>(rd: &mut R) -> Result<u8, ValueReadError<R::Error>> {
     rd.read_u8().map_err(ValueReadError::InvalidDataRead)
 }
 
-fn read_data_u16<R: Read>(rd: &mut R) -> Result<u16, ValueReadError> {
-    rd.read_u16::<byteorder::BigEndian>().map_err(ValueReadError::InvalidDataRead)
+fn read_data_u16<R: RmpRead>(rd: &mut R) -> Result<u16, ValueReadError<R::Error>> {
+    rd.read_data_u16().map_err(ValueReadError::InvalidDataRead)
 }
 
-fn read_data_u32<R: Read>(rd: &mut R) -> Result<u32, ValueReadError> {
-    rd.read_u32::<byteorder::BigEndian>().map_err(ValueReadError::InvalidDataRead)
+fn read_data_u32<R: RmpRead>(rd: &mut R) -> Result<u32, ValueReadError<R::Error>> {
+    rd.read_data_u32().map_err(ValueReadError::InvalidDataRead)
 }
 
-fn read_data_u64<R: Read>(rd: &mut R) -> Result<u64, ValueReadError> {
-    rd.read_u64::<byteorder::BigEndian>().map_err(ValueReadError::InvalidDataRead)
+fn read_data_u64<R: RmpRead>(rd: &mut R) -> Result<u64, ValueReadError<R::Error>> {
+    rd.read_data_u64().map_err(ValueReadError::InvalidDataRead)
 }
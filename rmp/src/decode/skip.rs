@@ -0,0 +1,139 @@
+use Marker;
+use super::{read_data_u8, read_data_u16, read_data_u32, read_marker, RmpRead, ValueReadError};
+
+/// Attempts to advance the reader past exactly one complete MessagePack item, without
+/// materializing it.
+///
+/// This is useful for streaming parsers and schema-evolution scenarios, such as ignoring unknown
+/// map fields, where the value itself is of no interest. Rather than building up a `Value` tree,
+/// string/binary/extension payloads are discarded via `RmpRead::skip_bytes` instead of being
+/// copied into a buffer, and nested arrays/maps are tracked with a pending-item counter instead of
+/// recursing, so skipping a large subtree costs no more memory than skipping a scalar.
+///
+/// # Errors
+///
+/// This function will return `ValueReadError` on any I/O error while reading a marker or length
+/// prefix, except the EINTR, which is handled internally. It also returns
+/// `ValueReadError::TypeMismatch` for reserved markers.
+///
+/// # Note
+///
+/// This function will silently retry on every EINTR received from the underlying `Read` until
+/// successful read.
+pub fn skip_value<R: RmpRead>(rd: &mut R) -> Result<(), ValueReadError<R::Error>> {
+    let mut pending = 1u64;
+
+    while pending > 0 {
+        pending -= 1;
+
+        match try!(read_marker(rd)) {
+            Marker::Null |
+            Marker::True |
+            Marker::False |
+            Marker::FixPos(..) |
+            Marker::FixNeg(..) => {}
+            Marker::U8 | Marker::I8 => try!(skip_bytes(rd, 1)),
+            Marker::U16 | Marker::I16 => try!(skip_bytes(rd, 2)),
+            Marker::U32 | Marker::I32 | Marker::F32 => try!(skip_bytes(rd, 4)),
+            Marker::U64 | Marker::I64 | Marker::F64 => try!(skip_bytes(rd, 8)),
+            Marker::FixStr(len) => try!(skip_bytes(rd, len as u64)),
+            Marker::Str8 | Marker::Bin8 => {
+                let len = try!(read_data_u8(rd));
+                try!(skip_bytes(rd, len as u64));
+            }
+            Marker::Str16 | Marker::Bin16 => {
+                let len = try!(read_data_u16(rd));
+                try!(skip_bytes(rd, len as u64));
+            }
+            Marker::Str32 | Marker::Bin32 => {
+                let len = try!(read_data_u32(rd));
+                try!(skip_bytes(rd, len as u64));
+            }
+            Marker::FixArray(len) => pending += len as u64,
+            Marker::Array16 => pending += try!(read_data_u16(rd)) as u64,
+            Marker::Array32 => pending += try!(read_data_u32(rd)) as u64,
+            Marker::FixMap(len) => pending += 2 * len as u64,
+            Marker::Map16 => pending += 2 * try!(read_data_u16(rd)) as u64,
+            Marker::Map32 => pending += 2 * try!(read_data_u32(rd)) as u64,
+            Marker::FixExt1 => try!(skip_ext(rd, 1)),
+            Marker::FixExt2 => try!(skip_ext(rd, 2)),
+            Marker::FixExt4 => try!(skip_ext(rd, 4)),
+            Marker::FixExt8 => try!(skip_ext(rd, 8)),
+            Marker::FixExt16 => try!(skip_ext(rd, 16)),
+            Marker::Ext8 => {
+                let len = try!(read_data_u8(rd)) as u64;
+                try!(skip_ext(rd, len));
+            }
+            Marker::Ext16 => {
+                let len = try!(read_data_u16(rd)) as u64;
+                try!(skip_ext(rd, len));
+            }
+            Marker::Ext32 => {
+                let len = try!(read_data_u32(rd)) as u64;
+                try!(skip_ext(rd, len));
+            }
+            marker @ Marker::Reserved => return Err(ValueReadError::TypeMismatch(marker)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Consumes and discards exactly `len` bytes from the reader.
+fn skip_bytes<R: RmpRead>(rd: &mut R, len: u64) -> Result<(), ValueReadError<R::Error>> {
+    rd.skip_bytes(len).map_err(ValueReadError::InvalidDataRead)
+}
+
+/// Consumes and discards an extension payload: its 1-byte type id followed by `len` bytes.
+fn skip_ext<R: RmpRead>(rd: &mut R, len: u64) -> Result<(), ValueReadError<R::Error>> {
+    try!(skip_bytes(rd, 1));
+    skip_bytes(rd, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Bytes;
+
+    #[test]
+    fn skip_value_advances_past_a_scalar() {
+        let buf = [0xcc, 0x2a, 0xc0];
+        let mut rd = &buf[..];
+
+        skip_value(&mut rd).unwrap();
+
+        assert_eq!(&buf[2..], rd);
+    }
+
+    #[test]
+    fn skip_value_advances_past_nested_array_and_map() {
+        // [1, {"k": [2, 3]}], followed by a trailing nil marker.
+        let buf = [0x92, 0x01, 0x81, 0xa1, b'k', 0x92, 0x02, 0x03, 0xc0];
+        let mut rd = &buf[..];
+
+        skip_value(&mut rd).unwrap();
+
+        assert_eq!(&buf[8..], rd);
+    }
+
+    #[test]
+    fn skip_value_works_with_the_bytes_cursor_too() {
+        let buf = [0x91, 0xa3, b'f', b'o', b'o', 0xc0];
+        let mut rd = Bytes::new(&buf);
+
+        skip_value(&mut rd).unwrap();
+
+        assert_eq!(&buf[5..], rd.remainder());
+    }
+
+    #[test]
+    fn skip_value_on_truncated_ext_payload_fails() {
+        // Ext8 marker claiming 4 bytes of payload, but only 1 is actually present.
+        let buf = [0xc7, 0x04, 0x01, 0x00];
+
+        match skip_value(&mut &buf[..]) {
+            Err(ValueReadError::InvalidDataRead(..)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,230 @@
+#[cfg(feature = "std")]
+use std::fmt::{self, Display, Formatter};
+#[cfg(not(feature = "std"))]
+use core::fmt::{self, Display, Formatter};
+#[cfg(feature = "std")]
+use std::str::{self, Utf8Error};
+#[cfg(not(feature = "std"))]
+use core::str::{self, Utf8Error};
+
+#[cfg(feature = "std")]
+use std::error;
+
+use Marker;
+use super::{read_data_u8, read_data_u16, read_data_u32, read_marker, Bytes, DefaultError,
+            EndOfBuffer, RmpRead, RmpReadErr, ValueReadError};
+
+/// An error which can occur when attempting to read a MessagePack-encoded UTF-8 string.
+#[derive(Debug)]
+pub enum DecodeStringError<'a, E: RmpReadErr = DefaultError> {
+    /// Failed to read the marker or length prefix.
+    InvalidMarkerRead(E),
+    /// Failed to read the string payload.
+    InvalidDataRead(E),
+    /// The type decoded isn't match with the expected one.
+    TypeMismatch(Marker),
+    /// The given buffer is not large enough to accommodate the actual string; carries the
+    /// string's encoded length.
+    BufferSizeTooSmall(u32),
+    /// The string payload is not valid UTF-8; carries the raw bytes for lossy recovery.
+    InvalidUtf8(&'a [u8], Utf8Error),
+}
+
+impl<'a, E: RmpReadErr> From<ValueReadError<E>> for DecodeStringError<'a, E> {
+    fn from(err: ValueReadError<E>) -> DecodeStringError<'a, E> {
+        match err {
+            ValueReadError::InvalidMarkerRead(err) => DecodeStringError::InvalidMarkerRead(err),
+            ValueReadError::InvalidDataRead(err) => DecodeStringError::InvalidDataRead(err),
+            ValueReadError::TypeMismatch(marker) => DecodeStringError::TypeMismatch(marker),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, E: RmpReadErr + error::Error> error::Error for DecodeStringError<'a, E> {
+    fn description(&self) -> &str {
+        match *self {
+            DecodeStringError::InvalidMarkerRead(..) => "failed to read MessagePack marker",
+            DecodeStringError::InvalidDataRead(..) => "failed to read MessagePack data",
+            DecodeStringError::TypeMismatch(..) => {
+                "the type decoded isn't match with the expected one"
+            }
+            DecodeStringError::BufferSizeTooSmall(..) => {
+                "buffer is not large enough to accommodate the string"
+            }
+            DecodeStringError::InvalidUtf8(..) => "the decoded string is not valid UTF-8",
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            DecodeStringError::InvalidMarkerRead(ref err) => Some(err),
+            DecodeStringError::InvalidDataRead(ref err) => Some(err),
+            DecodeStringError::InvalidUtf8(_, ref err) => Some(err),
+            DecodeStringError::TypeMismatch(..) |
+            DecodeStringError::BufferSizeTooSmall(..) => None,
+        }
+    }
+}
+
+impl<'a, E: RmpReadErr> Display for DecodeStringError<'a, E> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            DecodeStringError::InvalidMarkerRead(ref err) => {
+                write!(f, "failed to read MessagePack marker: {}", err)
+            }
+            DecodeStringError::InvalidDataRead(ref err) => {
+                write!(f, "failed to read MessagePack data: {}", err)
+            }
+            DecodeStringError::TypeMismatch(..) => {
+                "the type decoded isn't match with the expected one".fmt(f)
+            }
+            DecodeStringError::BufferSizeTooSmall(len) => {
+                write!(f, "buffer size is too small, expected at least {} bytes", len)
+            }
+            DecodeStringError::InvalidUtf8(_, ref err) => {
+                write!(f, "the decoded string is not valid UTF-8: {}", err)
+            }
+        }
+    }
+}
+
+/// Attempts to read a string marker and to return the length of the string that follows it.
+///
+/// This handles `FixStr`, `Str8`, `Str16` and `Str32`.
+pub fn read_str_len<R: RmpRead>(rd: &mut R) -> Result<u32, ValueReadError<R::Error>> {
+    match try!(read_marker(rd)) {
+        Marker::FixStr(len) => Ok(len as u32),
+        Marker::Str8 => Ok(try!(read_data_u8(rd)) as u32),
+        Marker::Str16 => Ok(try!(read_data_u16(rd)) as u32),
+        Marker::Str32 => Ok(try!(read_data_u32(rd))),
+        marker => Err(ValueReadError::TypeMismatch(marker)),
+    }
+}
+
+/// Attempts to read a MessagePack string into the given buffer, returning the decoded `&str`
+/// borrowed from it.
+///
+/// This avoids allocating an intermediate `String`: the caller supplies the storage, and this
+/// function only validates that the payload is valid UTF-8.
+///
+/// # Errors
+///
+/// Returns `DecodeStringError::BufferSizeTooSmall` if `buf` is too small to hold the string, and
+/// `DecodeStringError::InvalidUtf8` if the payload is not valid UTF-8.
+pub fn read_str<'r, R: RmpRead>(rd: &mut R,
+                                buf: &'r mut [u8])
+                                -> Result<&'r str, DecodeStringError<'r, R::Error>> {
+    let len = try!(read_str_len(rd));
+
+    if (buf.len() as u32) < len {
+        return Err(DecodeStringError::BufferSizeTooSmall(len));
+    }
+
+    let buf = &mut buf[0..len as usize];
+    try!(rd.read_exact_buf(buf).map_err(ValueReadError::InvalidDataRead));
+
+    match str::from_utf8(buf) {
+        Ok(s) => Ok(s),
+        Err(err) => Err(DecodeStringError::InvalidUtf8(buf, err)),
+    }
+}
+
+/// Attempts to read a MessagePack string directly out of an in-memory buffer, without copying.
+///
+/// On success, returns the decoded `&str` borrowed from `buf` together with the unconsumed tail
+/// of `buf` that follows it, so the caller can keep decoding further items from the same buffer.
+pub fn read_str_from_slice<'a>(buf: &'a [u8])
+                                -> Result<(&'a str, &'a [u8]), DecodeStringError<'a, EndOfBuffer>> {
+    let mut rd = Bytes::new(buf);
+    let len = try!(read_str_len(&mut rd)) as usize;
+
+    let rest = rd.remainder();
+    if rest.len() < len {
+        return Err(DecodeStringError::InvalidDataRead(EndOfBuffer));
+    }
+
+    let (data, tail) = rest.split_at(len);
+
+    match str::from_utf8(data) {
+        Ok(s) => Ok((s, tail)),
+        Err(err) => Err(DecodeStringError::InvalidUtf8(data, err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_str_len_reads_fixstr_and_str8() {
+        let fixstr = [0xa3, b'f', b'o', b'o'];
+        assert_eq!(3, read_str_len(&mut &fixstr[..]).unwrap());
+
+        let str8 = [0xd9, 0x05];
+        assert_eq!(5, read_str_len(&mut &str8[..]).unwrap());
+    }
+
+    #[test]
+    fn read_str_copies_into_caller_buffer() {
+        let input = [0xa3, b'f', b'o', b'o'];
+        let mut buf = [0u8; 3];
+
+        let s = read_str(&mut &input[..], &mut buf).unwrap();
+
+        assert_eq!("foo", s);
+    }
+
+    #[test]
+    fn read_str_reports_buffer_too_small() {
+        let input = [0xa3, b'f', b'o', b'o'];
+        let mut buf = [0u8; 2];
+
+        match read_str(&mut &input[..], &mut buf) {
+            Err(DecodeStringError::BufferSizeTooSmall(3)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_str_rejects_invalid_utf8() {
+        let input = [0xa1, 0xff];
+        let mut buf = [0u8; 1];
+
+        match read_str(&mut &input[..], &mut buf) {
+            Err(DecodeStringError::InvalidUtf8(..)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_str_from_slice_borrows_and_returns_tail() {
+        let input = [0xa3, b'f', b'o', b'o', 0xc0];
+
+        let (s, tail) = read_str_from_slice(&input).unwrap();
+
+        assert_eq!("foo", s);
+        assert_eq!(&input[4..], tail);
+    }
+
+    #[test]
+    fn read_str_from_slice_rejects_invalid_utf8() {
+        let input = [0xa1, 0xff];
+
+        match read_str_from_slice(&input) {
+            Err(DecodeStringError::InvalidUtf8(..)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_str_from_slice_reports_short_buffer() {
+        // FixStr claims 3 bytes but only 1 is actually present.
+        let input = [0xa3, b'f'];
+
+        match read_str_from_slice(&input) {
+            Err(DecodeStringError::InvalidDataRead(EndOfBuffer)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
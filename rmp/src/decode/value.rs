@@ -0,0 +1,388 @@
+use std::cmp;
+use std::fmt::{self, Display, Formatter};
+use std::str::Utf8Error;
+
+#[cfg(feature = "std")]
+use std::error;
+
+use Marker;
+use super::{read_data_i8, read_data_i16, read_data_i32, read_data_i64, read_data_u8,
+            read_data_u16, read_data_u32, read_data_u64, read_marker, Error, MarkerReadError,
+            RmpRead, RmpReadErr, ValueReadError};
+
+/// The default maximum recursion depth used by `read_value`.
+///
+/// This bounds how deeply nested arrays and maps may be before decoding gives up, which protects
+/// against a stack overflow triggered by hostile, deeply nested input.
+pub const DEFAULT_MAX_DEPTH: usize = 1024;
+
+/// The chunk size used when reading string/binary/extension payloads.
+///
+/// Payload lengths come straight from an untrusted length prefix, so the payload is read in
+/// bounded chunks rather than allocated up front: a `Bin32`/`Str32`/`Ext32` header claiming a
+/// multi-gigabyte length can then only grow the buffer as far as data actually exists to back it,
+/// instead of forcing an immediate multi-gigabyte allocation.
+const READ_CHUNK_SIZE: usize = 8 * 1024;
+
+/// A MessagePack integer, preserving the signedness it was encoded with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Integer {
+    /// An integer that was encoded as unsigned.
+    U64(u64),
+    /// An integer that was encoded as signed.
+    I64(i64),
+}
+
+/// A dynamically-typed MessagePack value, as decoded by `read_value`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Nil.
+    Nil,
+    /// A boolean.
+    Boolean(bool),
+    /// An integer.
+    Integer(Integer),
+    /// A 32-bit floating point number.
+    F32(f32),
+    /// A 64-bit floating point number.
+    F64(f64),
+    /// A UTF-8 string.
+    String(String),
+    /// Raw binary data.
+    Binary(Vec<u8>),
+    /// An array of values.
+    Array(Vec<Value>),
+    /// A map of key/value pairs, in the order they were encountered.
+    Map(Vec<(Value, Value)>),
+    /// An application-defined extension type, as `(typeid, payload)`.
+    Ext(i8, Vec<u8>),
+}
+
+/// An error which can occur when attempting to read a MessagePack value into a `Value`.
+#[derive(Debug)]
+pub enum DecodeValueError<E: RmpReadErr = Error> {
+    /// Failed to read the marker.
+    InvalidMarkerRead(E),
+    /// Failed to read the data.
+    InvalidDataRead(E),
+    /// The type decoded isn't match with the expected one.
+    TypeMismatch(Marker),
+    /// A string's payload was not valid UTF-8.
+    InvalidUtf8(Utf8Error),
+    /// Nested arrays/maps exceeded the configured maximum recursion depth.
+    DepthLimitExceeded,
+}
+
+impl<E: RmpReadErr> From<ValueReadError<E>> for DecodeValueError<E> {
+    fn from(err: ValueReadError<E>) -> DecodeValueError<E> {
+        match err {
+            ValueReadError::InvalidMarkerRead(err) => DecodeValueError::InvalidMarkerRead(err),
+            ValueReadError::InvalidDataRead(err) => DecodeValueError::InvalidDataRead(err),
+            ValueReadError::TypeMismatch(marker) => DecodeValueError::TypeMismatch(marker),
+        }
+    }
+}
+
+impl<E: RmpReadErr> From<MarkerReadError<E>> for DecodeValueError<E> {
+    fn from(err: MarkerReadError<E>) -> DecodeValueError<E> {
+        match err {
+            MarkerReadError(err) => DecodeValueError::InvalidMarkerRead(err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: RmpReadErr + error::Error> error::Error for DecodeValueError<E> {
+    fn description(&self) -> &str {
+        match *self {
+            DecodeValueError::InvalidMarkerRead(..) => "failed to read MessagePack marker",
+            DecodeValueError::InvalidDataRead(..) => "failed to read MessagePack data",
+            DecodeValueError::TypeMismatch(..) => {
+                "the type decoded isn't match with the expected one"
+            }
+            DecodeValueError::InvalidUtf8(..) => "a string's payload is not valid UTF-8",
+            DecodeValueError::DepthLimitExceeded => {
+                "exceeded the maximum recursion depth while decoding a value"
+            }
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            DecodeValueError::InvalidMarkerRead(ref err) => Some(err),
+            DecodeValueError::InvalidDataRead(ref err) => Some(err),
+            DecodeValueError::InvalidUtf8(ref err) => Some(err),
+            DecodeValueError::TypeMismatch(..) |
+            DecodeValueError::DepthLimitExceeded => None,
+        }
+    }
+}
+
+impl<E: RmpReadErr> Display for DecodeValueError<E> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            DecodeValueError::InvalidMarkerRead(ref err) => {
+                write!(f, "failed to read MessagePack marker: {}", err)
+            }
+            DecodeValueError::InvalidDataRead(ref err) => {
+                write!(f, "failed to read MessagePack data: {}", err)
+            }
+            DecodeValueError::TypeMismatch(..) => {
+                "the type decoded isn't match with the expected one".fmt(f)
+            }
+            DecodeValueError::InvalidUtf8(ref err) => {
+                write!(f, "a string's payload is not valid UTF-8: {}", err)
+            }
+            DecodeValueError::DepthLimitExceeded => {
+                "exceeded the maximum recursion depth while decoding a value".fmt(f)
+            }
+        }
+    }
+}
+
+/// Attempts to read and decode an arbitrary MessagePack-encoded item from the given reader into
+/// an owned `Value`.
+///
+/// Unlike the type-specialized readers in this module, this function does not require the caller
+/// to know the type of the next item ahead of time: it reads the marker and recursively decodes
+/// whatever it finds.
+///
+/// Recursion into nested arrays and maps is bounded by `DEFAULT_MAX_DEPTH`; use
+/// `read_value_with_max_depth` to customize the limit.
+///
+/// # Errors
+///
+/// This function will return `DecodeValueError` on any I/O error while reading the value, except
+/// the EINTR, which is handled internally. It also returns `DecodeValueError::TypeMismatch` for
+/// reserved markers, `DecodeValueError::InvalidUtf8` if a string's payload is not valid UTF-8, and
+/// `DecodeValueError::DepthLimitExceeded` if the maximum recursion depth is exceeded.
+pub fn read_value<R: RmpRead>(rd: &mut R) -> Result<Value, DecodeValueError<R::Error>> {
+    read_value_with_max_depth(rd, DEFAULT_MAX_DEPTH)
+}
+
+/// Like `read_value`, but with a caller-supplied maximum recursion depth.
+pub fn read_value_with_max_depth<R: RmpRead>(rd: &mut R,
+                                              max_depth: usize)
+                                              -> Result<Value, DecodeValueError<R::Error>> {
+    if max_depth == 0 {
+        return Err(DecodeValueError::DepthLimitExceeded);
+    }
+
+    match try!(read_marker(rd)) {
+        Marker::Null => Ok(Value::Nil),
+        Marker::True => Ok(Value::Boolean(true)),
+        Marker::False => Ok(Value::Boolean(false)),
+        Marker::FixPos(val) => Ok(Value::Integer(Integer::U64(val as u64))),
+        Marker::FixNeg(val) => Ok(Value::Integer(Integer::I64(val as i64))),
+        Marker::U8 => Ok(Value::Integer(Integer::U64(try!(read_data_u8(rd)) as u64))),
+        Marker::U16 => Ok(Value::Integer(Integer::U64(try!(read_data_u16(rd)) as u64))),
+        Marker::U32 => Ok(Value::Integer(Integer::U64(try!(read_data_u32(rd)) as u64))),
+        Marker::U64 => Ok(Value::Integer(Integer::U64(try!(read_data_u64(rd))))),
+        Marker::I8 => Ok(Value::Integer(Integer::I64(try!(read_data_i8(rd)) as i64))),
+        Marker::I16 => Ok(Value::Integer(Integer::I64(try!(read_data_i16(rd)) as i64))),
+        Marker::I32 => Ok(Value::Integer(Integer::I64(try!(read_data_i32(rd)) as i64))),
+        Marker::I64 => Ok(Value::Integer(Integer::I64(try!(read_data_i64(rd))))),
+        Marker::F32 => Ok(Value::F32(f32::from_bits(try!(read_data_u32(rd))))),
+        Marker::F64 => Ok(Value::F64(f64::from_bits(try!(read_data_u64(rd))))),
+        Marker::FixStr(len) => read_str_payload(rd, len as u32).map(Value::String),
+        Marker::Str8 => {
+            let len = try!(read_data_u8(rd)) as u32;
+            read_str_payload(rd, len).map(Value::String)
+        }
+        Marker::Str16 => {
+            let len = try!(read_data_u16(rd)) as u32;
+            read_str_payload(rd, len).map(Value::String)
+        }
+        Marker::Str32 => {
+            let len = try!(read_data_u32(rd));
+            read_str_payload(rd, len).map(Value::String)
+        }
+        Marker::Bin8 => {
+            let len = try!(read_data_u8(rd)) as u32;
+            read_bin_payload(rd, len).map(Value::Binary)
+        }
+        Marker::Bin16 => {
+            let len = try!(read_data_u16(rd)) as u32;
+            read_bin_payload(rd, len).map(Value::Binary)
+        }
+        Marker::Bin32 => {
+            let len = try!(read_data_u32(rd));
+            read_bin_payload(rd, len).map(Value::Binary)
+        }
+        Marker::FixArray(len) => read_array(rd, len as u32, max_depth),
+        Marker::Array16 => {
+            let len = try!(read_data_u16(rd)) as u32;
+            read_array(rd, len, max_depth)
+        }
+        Marker::Array32 => {
+            let len = try!(read_data_u32(rd));
+            read_array(rd, len, max_depth)
+        }
+        Marker::FixMap(len) => read_map(rd, len as u32, max_depth),
+        Marker::Map16 => {
+            let len = try!(read_data_u16(rd)) as u32;
+            read_map(rd, len, max_depth)
+        }
+        Marker::Map32 => {
+            let len = try!(read_data_u32(rd));
+            read_map(rd, len, max_depth)
+        }
+        Marker::FixExt1 => read_ext_payload(rd, 1),
+        Marker::FixExt2 => read_ext_payload(rd, 2),
+        Marker::FixExt4 => read_ext_payload(rd, 4),
+        Marker::FixExt8 => read_ext_payload(rd, 8),
+        Marker::FixExt16 => read_ext_payload(rd, 16),
+        Marker::Ext8 => {
+            let len = try!(read_data_u8(rd)) as u32;
+            read_ext_payload(rd, len)
+        }
+        Marker::Ext16 => {
+            let len = try!(read_data_u16(rd)) as u32;
+            read_ext_payload(rd, len)
+        }
+        Marker::Ext32 => {
+            let len = try!(read_data_u32(rd));
+            read_ext_payload(rd, len)
+        }
+        marker @ Marker::Reserved => Err(DecodeValueError::TypeMismatch(marker)),
+    }
+}
+
+fn read_str_payload<R: RmpRead>(rd: &mut R, len: u32) -> Result<String, DecodeValueError<R::Error>> {
+    let buf = try!(read_bin_payload(rd, len));
+    String::from_utf8(buf).map_err(|err| DecodeValueError::InvalidUtf8(err.utf8_error()))
+}
+
+/// Reads exactly `len` bytes into a freshly allocated `Vec`, growing it in bounded chunks instead
+/// of allocating `len` bytes up front.
+///
+/// `len` is an attacker-controlled length prefix (up to `u32::MAX`), so pre-allocating it
+/// directly would let a single crafted header force a multi-gigabyte allocation before any
+/// payload byte has actually been read or shown to exist.
+fn read_bin_payload<R: RmpRead>(rd: &mut R, len: u32) -> Result<Vec<u8>, DecodeValueError<R::Error>> {
+    let len = len as usize;
+    let mut buf = Vec::with_capacity(cmp::min(len, READ_CHUNK_SIZE));
+
+    while buf.len() < len {
+        let chunk_len = cmp::min(len - buf.len(), READ_CHUNK_SIZE);
+        let start = buf.len();
+
+        buf.resize(start + chunk_len, 0);
+        try!(rd.read_exact_buf(&mut buf[start..]).map_err(ValueReadError::InvalidDataRead));
+    }
+
+    Ok(buf)
+}
+
+fn read_ext_payload<R: RmpRead>(rd: &mut R, len: u32) -> Result<Value, DecodeValueError<R::Error>> {
+    let typeid = try!(read_data_i8(rd));
+    let buf = try!(read_bin_payload(rd, len));
+    Ok(Value::Ext(typeid, buf))
+}
+
+fn read_array<R: RmpRead>(rd: &mut R,
+                           len: u32,
+                           max_depth: usize)
+                           -> Result<Value, DecodeValueError<R::Error>> {
+    let mut vec = Vec::with_capacity(0);
+    for _ in 0..len {
+        vec.push(try!(read_value_with_max_depth(rd, max_depth - 1)));
+    }
+    Ok(Value::Array(vec))
+}
+
+fn read_map<R: RmpRead>(rd: &mut R,
+                         len: u32,
+                         max_depth: usize)
+                         -> Result<Value, DecodeValueError<R::Error>> {
+    let mut vec = Vec::with_capacity(0);
+    for _ in 0..len {
+        let key = try!(read_value_with_max_depth(rd, max_depth - 1));
+        let val = try!(read_value_with_max_depth(rd, max_depth - 1));
+        vec.push((key, val));
+    }
+    Ok(Value::Map(vec))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_value_decodes_fixarray_of_scalars() {
+        let buf = [0x92, 0x01, 0xa3, b'f', b'o', b'o'];
+        let value = read_value(&mut &buf[..]).unwrap();
+
+        assert_eq!(Value::Array(vec![Value::Integer(Integer::U64(1)),
+                                      Value::String("foo".into())]),
+                   value);
+    }
+
+    #[test]
+    fn read_value_decodes_nested_fixmap() {
+        let buf = [0x81, 0xa1, b'k', 0xc0];
+        let value = read_value(&mut &buf[..]).unwrap();
+
+        assert_eq!(Value::Map(vec![(Value::String("k".into()), Value::Nil)]), value);
+    }
+
+    #[test]
+    fn read_value_with_max_depth_zero_fails_immediately() {
+        let buf = [0xc0];
+
+        match read_value_with_max_depth(&mut &buf[..], 0) {
+            Err(DecodeValueError::DepthLimitExceeded) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_value_rejects_array_nested_past_max_depth() {
+        // A single-element array containing a single-element array: depth 2.
+        let buf = [0x91, 0x91, 0xc0];
+
+        match read_value_with_max_depth(&mut &buf[..], 1) {
+            Err(DecodeValueError::DepthLimitExceeded) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // With enough depth budget (one level per nested array, plus one for the leaf value)
+        // the same input decodes fine.
+        let value = read_value_with_max_depth(&mut &buf[..], 3).unwrap();
+        assert_eq!(Value::Array(vec![Value::Array(vec![Value::Nil])]), value);
+    }
+
+    #[test]
+    fn read_value_on_truncated_input_is_invalid_data_read() {
+        // Bin8 marker claiming 4 bytes, but only 1 is actually present.
+        let buf = [0xc4, 0x04, 0x01];
+
+        match read_value(&mut &buf[..]) {
+            Err(DecodeValueError::InvalidDataRead(..)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_value_rejects_invalid_utf8_string() {
+        let buf = [0xa1, 0xff];
+
+        match read_value(&mut &buf[..]) {
+            Err(DecodeValueError::InvalidUtf8(..)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_bin_payload_does_not_preallocate_claimed_length() {
+        // Bin32 marker claiming ~4 GiB, with no payload at all. If this preallocated the claimed
+        // length up front, it would abort the test process trying to allocate ~4 GiB; instead it
+        // must fail promptly with a short read.
+        let buf = [0xc6, 0xff, 0xff, 0xff, 0xff];
+
+        match read_value(&mut &buf[..]) {
+            Err(DecodeValueError::InvalidDataRead(..)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}